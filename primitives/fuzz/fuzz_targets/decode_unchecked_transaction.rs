@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use codec::Slicable;
+use primitives::transaction::UncheckedTransaction;
+
+// `UncheckedTransaction::decode_bounded` must never panic or abort (including via
+// an over-large allocation driven by a forged length prefix in a nested field such
+// as `Function`) on arbitrary bytes, and anything it does accept must re-encode to
+// a prefix of the bytes it was given.
+fuzz_target!(|data: &[u8]| {
+	if let Some(tx) = UncheckedTransaction::decode_bounded(data) {
+		let encoded = Slicable::to_vec(&tx);
+		assert!(data.starts_with(&encoded));
+	}
+});
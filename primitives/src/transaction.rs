@@ -26,6 +26,141 @@ use std::fmt;
 #[cfg(not(feature = "std"))]
 use alloc::fmt;
 
+/// Split `len` bytes off the front of `value`, advancing it past them.
+fn take<'a>(value: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+	if value.len() < len {
+		return None;
+	}
+	let (taken, rest) = value.split_at(len);
+	*value = rest;
+	Some(taken)
+}
+
+/// The range of blocks, relative to signing time, for which a transaction is valid.
+///
+/// An immortal transaction can be replayed at any point in the chain's history; a
+/// mortal one is only includable within a fixed-size window, so a queued-but-never-
+/// included transaction can't come back to life long after its signer forgot about it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Era {
+	/// The transaction is valid forever.
+	Immortal,
+	/// The transaction is valid for `period` blocks, starting at `phase` blocks into
+	/// that period. Prefer building one with `Era::mortal`, which takes care of
+	/// quantization; `period` need not be a valid power of two here, since
+	/// encoding re-derives one defensively (see `Slicable::to_vec`).
+	Mortal {
+		/// Length, in blocks, of the era. `Era::mortal` always makes this a power of
+		/// two in `[4, 65536]`; encoding and `birth`/`death` clamp any other value
+		/// into that range.
+		period: u64,
+		/// Block number, modulo `period`, at which the era begins.
+		phase: u64,
+	},
+}
+
+impl Era {
+	/// Create a mortal era covering `period` blocks starting from `current`.
+	///
+	/// `period` is rounded up to the nearest power of two in `[4, 65536]`, and the
+	/// phase is quantized so that the era survives the lossy two-byte encoding.
+	pub fn mortal(period: u64, current: u64) -> Self {
+		let period = period.next_power_of_two().max(4).min(1 << 16);
+		let phase = current % period;
+		let quantize_factor = (period >> 12).max(1);
+		Era::Mortal { period, phase: phase / quantize_factor * quantize_factor }
+	}
+
+	/// The era's period, in blocks, or `None` for `Era::Immortal`.
+	pub fn period(self) -> Option<u64> {
+		match self {
+			Era::Immortal => None,
+			Era::Mortal { period, .. } => Some(period),
+		}
+	}
+
+	/// The era's phase, or `None` for `Era::Immortal`.
+	pub fn phase(self) -> Option<u64> {
+		match self {
+			Era::Immortal => None,
+			Era::Mortal { phase, .. } => Some(phase),
+		}
+	}
+
+	/// `period`/`phase` are public and so can be set to anything via a struct
+	/// literal (or deserialized straight off the wire under `feature = "std"`),
+	/// bypassing `Era::mortal`'s quantization. Re-derive a valid power-of-two
+	/// period and an in-range phase so nothing downstream can divide by zero or
+	/// underflow on `trailing_zeros`.
+	fn clamped(self) -> Self {
+		match self {
+			Era::Immortal => Era::Immortal,
+			Era::Mortal { period, phase } => {
+				let period = period.next_power_of_two().max(4).min(1 << 16);
+				Era::Mortal { period, phase: phase.min(period - 1) }
+			}
+		}
+	}
+
+	/// The earliest block number, given `current` was the block seen at signing
+	/// time, at which this era permits the transaction to be included.
+	pub fn birth(self, current: u64) -> u64 {
+		match self.clamped() {
+			Era::Immortal => 0,
+			Era::Mortal { period, phase } => (current.max(phase) - phase) / period * period + phase,
+		}
+	}
+
+	/// The latest block number, given `current` was the block seen at signing
+	/// time, at which this era permits the transaction to be included.
+	pub fn death(self, current: u64) -> u64 {
+		match self.clamped() {
+			Era::Immortal => u64::max_value(),
+			Era::Mortal { period, .. } => self.birth(current) + period - 1,
+		}
+	}
+}
+
+impl Slicable for Era {
+	fn from_slice(value: &mut &[u8]) -> Option<Self> {
+		let first = try_opt!(take(value, 1))[0];
+		if first == 0 {
+			return Some(Era::Immortal);
+		}
+		let second = try_opt!(take(value, 1))[0];
+		let encoded = first as u64 | ((second as u64) << 8);
+
+		let period = 2 << (encoded % (1 << 4));
+		let quantize_factor = (period >> 12).max(1);
+		let phase = (encoded >> 4) * quantize_factor;
+		if period >= 4 && phase < period {
+			Some(Era::Mortal { period, phase })
+		} else {
+			None
+		}
+	}
+
+	fn to_vec(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match self.clamped() {
+			Era::Immortal => v.push(0u8),
+			Era::Mortal { period, phase } => {
+				let quantize_factor = (period >> 12).max(1);
+				let encoded = (period.trailing_zeros() - 1).max(1).min(15) as u16
+					| (((phase / quantize_factor) as u16) << 4);
+				v.push((encoded & 0xff) as u8);
+				v.push((encoded >> 8) as u8);
+			}
+		}
+		v
+	}
+
+	fn as_slice_then<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(self.to_vec().as_slice())
+	}
+}
+
 /// A vetted and verified transaction from the external world.
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -34,6 +169,8 @@ pub struct Transaction {
 	pub signed: ::AccountId,
 	/// The number of transactions have come before from the same signer.
 	pub nonce: ::TxOrder,
+	/// The window of blocks within which this transaction may be included.
+	pub era: Era,
 	/// The function that should be called.
 	pub function: Function,
 }
@@ -43,6 +180,7 @@ impl Slicable for Transaction {
 		Some(Transaction {
 			signed: try_opt!(Slicable::from_slice(value)),
 			nonce: try_opt!(Slicable::from_slice(value)),
+			era: try_opt!(Slicable::from_slice(value)),
 			function: try_opt!(Slicable::from_slice(value)),
 		})
 	}
@@ -52,6 +190,7 @@ impl Slicable for Transaction {
 
 		self.signed.as_slice_then(|s| v.extend(s));
 		self.nonce.as_slice_then(|s| v.extend(s));
+		self.era.as_slice_then(|s| v.extend(s));
 		self.function.as_slice_then(|s| v.extend(s));
 
 		v
@@ -68,7 +207,8 @@ impl Slicable for Transaction {
 pub struct UncheckedTransaction {
 	/// The actual transaction information.
 	pub transaction: Transaction,
-	/// The signature; should be an Ed25519 signature applied to the serialised `transaction` field.
+	/// The signature; an Ed25519 signature over this transaction's `SignedPayload`,
+	/// which folds in chain constants that are never transmitted alongside it.
 	pub signature: ::Signature,
 }
 
@@ -85,6 +225,7 @@ impl Slicable for UncheckedTransaction {
 
 		self.transaction.signed.as_slice_then(|s| v.extend(s));
 		self.transaction.nonce.as_slice_then(|s| v.extend(s));
+		self.transaction.era.as_slice_then(|s| v.extend(s));
 		self.transaction.function.as_slice_then(|s| v.extend(s));
 		self.signature.as_slice_then(|s| v.extend(s));
 
@@ -98,9 +239,47 @@ impl Slicable for UncheckedTransaction {
 
 impl ::codec::NonTrivialSlicable for UncheckedTransaction {}
 
+/// Largest encoded size, in bytes, a single `UncheckedTransaction` may claim to be.
+/// `decode_bounded` rejects any input longer than this before it parses anything.
+pub const MAX_TX_SIZE: usize = 4 * 1024 * 1024;
+
+impl UncheckedTransaction {
+	/// Decode a single `UncheckedTransaction` from `data`, rejecting it outright
+	/// if `data` is larger than `MAX_TX_SIZE` or if any bytes are left over once
+	/// the transaction and signature have been read off the front.
+	///
+	/// Unlike `Slicable::from_slice`, which is also used to decode a transaction
+	/// embedded within a larger structure and so must tolerate trailing bytes
+	/// belonging to whatever follows, this is for decoding a whole message.
+	///
+	/// This ceiling only bounds the *outer* buffer; it does not by itself bound
+	/// allocation a nested variable-length field might do from a forged length
+	/// prefix a few bytes in (`Function`, reached via `Transaction::from_slice`,
+	/// is the field this backlog item called out). The decoders in this file that
+	/// read raw byte runs (`Era`, and the length-prefixed records of
+	/// `PartiallySignedTransaction`/`FinalizedTransaction`) take them as slices of
+	/// the bytes actually remaining via `take`,
+	/// which returns `None` rather than allocating if the claimed length exceeds
+	/// what's left, instead of trusting the prefix and allocating ahead of it.
+	/// `Function` lives in the separate `runtime_function` crate and must uphold
+	/// the same invariant; it is not defined in this crate so it cannot be fixed
+	/// here if it doesn't.
+	pub fn decode_bounded(data: &[u8]) -> Option<Self> {
+		if data.len() > MAX_TX_SIZE {
+			return None;
+		}
+		let mut remaining = data;
+		let tx = try_opt!(Self::from_slice(&mut remaining));
+		if !remaining.is_empty() {
+			return None;
+		}
+		Some(tx)
+	}
+}
+
 impl PartialEq for UncheckedTransaction {
 	fn eq(&self, other: &Self) -> bool {
-		self.signature.iter().eq(other.signature.iter()) && self.transaction == other.transaction
+		signatures_eq(&self.signature, &other.signature) && self.transaction == other.transaction
 	}
 }
 
@@ -110,24 +289,464 @@ impl fmt::Debug for UncheckedTransaction {
 	}
 }
 
+/// Context that a signer commits to but that never travels on the wire, because
+/// every honest party can derive it locally from the chain it's following.
+///
+/// Concatenating this with the `Transaction` (which already carries the `Era`)
+/// before hashing means a signature is only valid for one genesis block and one
+/// runtime spec version, so a transaction signed for chain A, or for an old
+/// runtime, can't be replayed against chain B or a newer runtime.
+pub struct SignedPayload<'a> {
+	transaction: &'a Transaction,
+	genesis_hash: ::hash::H256,
+	spec_version: u32,
+}
+
+impl<'a> SignedPayload<'a> {
+	/// Build the payload that must be signed for `transaction` against the given
+	/// chain constants.
+	pub fn new(transaction: &'a Transaction, genesis_hash: ::hash::H256, spec_version: u32) -> Self {
+		SignedPayload { transaction, genesis_hash, spec_version }
+	}
+
+	/// Run `f` against the encoded bytes that get hashed and signed.
+	pub fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		let mut v = self.transaction.to_vec();
+		self.genesis_hash.as_slice_then(|s| v.extend(s));
+		self.spec_version.as_slice_then(|s| v.extend(s));
+		f(&v)
+	}
+}
+
+impl UncheckedTransaction {
+	/// Check `self.signature` against the `SignedPayload` reconstructed from the
+	/// transaction plus the given chain constants.
+	pub fn verify(&self, genesis_hash: ::hash::H256, spec_version: u32) -> bool {
+		let payload = SignedPayload::new(&self.transaction, genesis_hash, spec_version);
+		payload.using_encoded(|msg| ::ed25519::verify(&self.signature, msg, &self.transaction.signed))
+	}
+}
+
+/// Error produced while assembling a `PartiallySignedTransaction`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PsbtError {
+	/// `combine` was given a partial signature set for a different `Transaction`.
+	TransactionMismatch,
+	/// Two sources signed the same account with a different signature.
+	ConflictingSignature(::AccountId),
+	/// `finalize` was called without signatures from every required signer.
+	MissingSignature(::AccountId),
+}
+
+/// `Signature` doesn't implement `PartialEq` (see `UncheckedTransaction`'s hand
+/// written `impl PartialEq` above), so anything that wants to compare two of
+/// them needs this instead of `==`.
+fn signatures_eq(a: &::Signature, b: &::Signature) -> bool {
+	a.iter().eq(b.iter())
+}
+
+/// As `signatures_eq`, but for the `(AccountId, Signature)` lists stored in
+/// `PartiallySignedTransaction`/`FinalizedTransaction`.
+fn signature_records_eq(a: &[(::AccountId, ::Signature)], b: &[(::AccountId, ::Signature)]) -> bool {
+	a.len() == b.len()
+		&& a.iter().zip(b).all(|((a_account, a_sig), (b_account, b_sig))| {
+			a_account == b_account && signatures_eq(a_sig, b_sig)
+		})
+}
+
+/// Encode `signatures` as the sequence of `<key-len><key><value-len><value>`
+/// records, terminated by a `0x00` length byte, shared by
+/// `PartiallySignedTransaction` and `FinalizedTransaction`.
+fn encode_signature_records(signatures: &[(::AccountId, ::Signature)], v: &mut Vec<u8>) {
+	for (account, signature) in signatures {
+		(::core::mem::size_of::<::AccountId>() as u8).as_slice_then(|s| v.extend(s));
+		account.as_slice_then(|s| v.extend(s));
+		(::core::mem::size_of_val(signature) as u8).as_slice_then(|s| v.extend(s));
+		signature.as_slice_then(|s| v.extend(s));
+	}
+	0u8.as_slice_then(|s| v.extend(s));
+}
+
+/// Decode the record sequence written by `encode_signature_records`.
+///
+/// Records we don't recognise are skipped rather than rejected, so a future
+/// record type can be added without breaking older signers. Two records for the
+/// same signer that disagree make the whole decode fail, mirroring `combine`'s
+/// conflict check.
+fn decode_signature_records(value: &mut &[u8]) -> Option<Vec<(::AccountId, ::Signature)>> {
+	let mut signatures: Vec<(::AccountId, ::Signature)> = Vec::new();
+
+	loop {
+		let key_len: u8 = try_opt!(Slicable::from_slice(value));
+		if key_len == 0 {
+			break;
+		}
+		let key = try_opt!(take(value, key_len as usize));
+
+		let value_len: u8 = try_opt!(Slicable::from_slice(value));
+		let val = try_opt!(take(value, value_len as usize));
+
+		if key.len() == ::core::mem::size_of::<::AccountId>()
+			&& val.len() == ::core::mem::size_of::<::Signature>()
+		{
+			let account: ::AccountId = try_opt!(Slicable::from_slice(&mut &key[..]));
+			let signature: ::Signature = try_opt!(Slicable::from_slice(&mut &val[..]));
+
+			match signatures.iter().find(|(a, _)| *a == account) {
+				Some((_, existing)) if signatures_eq(existing, &signature) => {}
+				Some(_) => return None,
+				None => signatures.push((account, signature)),
+			}
+		}
+	}
+
+	Some(signatures)
+}
+
+/// A `Transaction` together with the partial signatures collected for it so far.
+///
+/// Modeled on BIP174's separable PSBT workflow: a wallet builds the `Transaction`,
+/// distributes it to each signer, and `combine`s the results back together once
+/// every required signature has been gathered, before `finalize`-ing into a
+/// finished transaction.
+#[derive(Debug, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct PartiallySignedTransaction {
+	/// The transaction being signed.
+	pub transaction: Transaction,
+	/// Partial signatures collected so far, keyed by signer.
+	signatures: Vec<(::AccountId, ::Signature)>,
+}
+
+impl PartialEq for PartiallySignedTransaction {
+	fn eq(&self, other: &Self) -> bool {
+		self.transaction == other.transaction && signature_records_eq(&self.signatures, &other.signatures)
+	}
+}
+
+/// A `Transaction` paired with every signature required to authorize it.
+///
+/// This is the multisig counterpart to `UncheckedTransaction`: where the latter
+/// carries exactly one signature, a `FinalizedTransaction` carries one per signer.
+#[derive(Debug, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct FinalizedTransaction {
+	/// The finalized transaction.
+	pub transaction: Transaction,
+	/// The signatures authorizing it, keyed by signer.
+	pub signatures: Vec<(::AccountId, ::Signature)>,
+}
+
+impl PartialEq for FinalizedTransaction {
+	fn eq(&self, other: &Self) -> bool {
+		self.transaction == other.transaction && signature_records_eq(&self.signatures, &other.signatures)
+	}
+}
+
+impl PartiallySignedTransaction {
+	/// Begin collecting signatures for `transaction`.
+	pub fn new(transaction: Transaction) -> Self {
+		PartiallySignedTransaction { transaction, signatures: Vec::new() }
+	}
+
+	/// Record `signature` as having come from `account`, overwriting any previous
+	/// signature recorded for that account.
+	pub fn add_signature(&mut self, account: ::AccountId, signature: ::Signature) {
+		if let Some(entry) = self.signatures.iter_mut().find(|(a, _)| *a == account) {
+			entry.1 = signature;
+		} else {
+			self.signatures.push((account, signature));
+		}
+	}
+
+	/// Merge the signatures collected by `other` into `self`.
+	///
+	/// Errors if `other` was built for a different `Transaction`, or if the two
+	/// sets disagree on the signature for the same account.
+	pub fn combine(&mut self, other: Self) -> Result<(), PsbtError> {
+		if self.transaction != other.transaction {
+			return Err(PsbtError::TransactionMismatch);
+		}
+		for (account, signature) in other.signatures {
+			match self.signatures.iter().find(|(a, _)| *a == account) {
+				Some((_, existing)) if signatures_eq(existing, &signature) => {}
+				Some(_) => return Err(PsbtError::ConflictingSignature(account)),
+				None => self.signatures.push((account, signature)),
+			}
+		}
+		Ok(())
+	}
+
+	/// Collapse into a `FinalizedTransaction`, provided a signature has been
+	/// collected from every account in `required`.
+	pub fn finalize(self, required: &[::AccountId]) -> Result<FinalizedTransaction, PsbtError> {
+		for account in required {
+			if !self.signatures.iter().any(|(a, _)| a == account) {
+				return Err(PsbtError::MissingSignature(*account));
+			}
+		}
+		Ok(FinalizedTransaction { transaction: self.transaction, signatures: self.signatures })
+	}
+}
+
+impl Slicable for PartiallySignedTransaction {
+	fn from_slice(value: &mut &[u8]) -> Option<Self> {
+		let transaction = try_opt!(Transaction::from_slice(value));
+		let signatures = try_opt!(decode_signature_records(value));
+		Some(PartiallySignedTransaction { transaction, signatures })
+	}
+
+	fn to_vec(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		self.transaction.as_slice_then(|s| v.extend(s));
+		encode_signature_records(&self.signatures, &mut v);
+		v
+	}
+
+	fn as_slice_then<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(self.to_vec().as_slice())
+	}
+}
+
+impl ::codec::NonTrivialSlicable for PartiallySignedTransaction {}
+
+impl Slicable for FinalizedTransaction {
+	fn from_slice(value: &mut &[u8]) -> Option<Self> {
+		let transaction = try_opt!(Transaction::from_slice(value));
+		let signatures = try_opt!(decode_signature_records(value));
+		Some(FinalizedTransaction { transaction, signatures })
+	}
+
+	fn to_vec(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		self.transaction.as_slice_then(|s| v.extend(s));
+		encode_signature_records(&self.signatures, &mut v);
+		v
+	}
+
+	fn as_slice_then<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(self.to_vec().as_slice())
+	}
+}
+
+impl ::codec::NonTrivialSlicable for FinalizedTransaction {}
+
 #[cfg(test)]
 mod tests {
 	use ::codec::Slicable;
 	use runtime_function::Function;
 	use super::*;
 
+	fn a_tx() -> Transaction {
+		Transaction {
+			signed: [1; 32],
+			nonce: 999u64,
+			era: Era::Immortal,
+			function: Function::TimestampSet(135135),
+		}
+	}
+
+	#[test]
+	fn era_immortal_round_trips() {
+		let v = Slicable::to_vec(&Era::Immortal);
+		assert_eq!(v, vec![0]);
+		assert_eq!(Era::from_slice(&mut &v[..]).unwrap(), Era::Immortal);
+	}
+
+	#[test]
+	fn era_mortal_round_trips() {
+		let era = Era::mortal(256, 42);
+		let v = Slicable::to_vec(&era);
+		assert_eq!(v.len(), 2);
+		assert_eq!(Era::from_slice(&mut &v[..]).unwrap(), era);
+	}
+
+	#[test]
+	fn era_mortal_to_vec_does_not_panic_on_invalid_period() {
+		// `Era::Mortal`'s fields are public, so this bypasses `Era::mortal`'s
+		// quantization; `to_vec` must not underflow computing the encoding.
+		let era = Era::Mortal { period: 1, phase: 0 };
+		let v = Slicable::to_vec(&era);
+		assert_eq!(v.len(), 2);
+	}
+
+	#[test]
+	fn era_mortal_birth_and_death_do_not_panic_on_invalid_period() {
+		// As above: a zero period would otherwise divide by zero in `birth`.
+		let era = Era::Mortal { period: 0, phase: 0 };
+		let birth = era.birth(1000);
+		assert!(birth <= 1000);
+		assert!(era.death(1000) > birth);
+	}
+
+	#[test]
+	fn era_mortal_birth_and_death() {
+		let era = Era::mortal(256, 1000);
+		let birth = era.birth(1000);
+		assert!(birth <= 1000);
+		assert_eq!(era.death(1000), birth + 255);
+	}
+
+	#[test]
+	fn signed_payload_differs_by_chain_constant() {
+		let tx = a_tx();
+		let one = SignedPayload::new(&tx, ::hash::H256([0; 32]), 1);
+		let two = SignedPayload::new(&tx, ::hash::H256([0; 32]), 2);
+
+		let one = one.using_encoded(|s| s.to_vec());
+		let two = two.using_encoded(|s| s.to_vec());
+		assert_ne!(one, two);
+		assert!(one.starts_with(&Slicable::to_vec(&tx)));
+	}
+
+	#[test]
+	fn verify_accepts_matching_and_rejects_mismatched_chain_constants() {
+		let pair = ::ed25519::Pair::from_seed(&[1; 32]);
+
+		let mut tx = a_tx();
+		tx.signed = pair.public();
+
+		let genesis_hash = ::hash::H256([2; 32]);
+		let spec_version = 7;
+
+		let signature = SignedPayload::new(&tx, genesis_hash, spec_version)
+			.using_encoded(|msg| pair.sign(msg));
+		let unchecked = UncheckedTransaction { transaction: tx, signature };
+
+		assert!(unchecked.verify(genesis_hash, spec_version));
+		assert!(!unchecked.verify(::hash::H256([3; 32]), spec_version));
+		assert!(!unchecked.verify(genesis_hash, spec_version + 1));
+	}
+
+	#[test]
+	fn decode_bounded_accepts_exact_encoding() {
+		let tx = UncheckedTransaction {
+			transaction: a_tx(),
+			signature: ::hash::H512([0; 64]),
+		};
+
+		let v = Slicable::to_vec(&tx);
+		assert_eq!(UncheckedTransaction::decode_bounded(&v), Some(tx));
+	}
+
+	#[test]
+	fn decode_bounded_rejects_trailing_garbage() {
+		let tx = UncheckedTransaction {
+			transaction: a_tx(),
+			signature: ::hash::H512([0; 64]),
+		};
+
+		let mut v = Slicable::to_vec(&tx);
+		v.push(0xff);
+		assert_eq!(UncheckedTransaction::decode_bounded(&v), None);
+	}
+
+	#[test]
+	fn decode_bounded_rejects_oversized_input() {
+		let v = vec![0u8; MAX_TX_SIZE + 1];
+		assert_eq!(UncheckedTransaction::decode_bounded(&v), None);
+	}
+
+	#[test]
+	fn decode_bounded_rejects_truncated_function_field() {
+		// Real bytes for `signed`, `nonce` and `era`, but nothing left for
+		// `function` (or the signature): `Function::from_slice` must see an
+		// empty remaining slice and fail rather than reading past the end.
+		let mut v = Vec::new();
+		v.extend_from_slice(&[1; 32]);
+		999u64.as_slice_then(|s| v.extend(s));
+		Era::Immortal.as_slice_then(|s| v.extend(s));
+
+		assert_eq!(UncheckedTransaction::decode_bounded(&v), None);
+	}
+
 	#[test]
 	fn serialize_unchecked() {
 		let tx = UncheckedTransaction {
-			transaction: Transaction {
-				signed: [1; 32],
-				nonce: 999u64,
-				function: Function::TimestampSet(135135),
-			},
+			transaction: a_tx(),
 			signature: ::hash::H512([0; 64]),
 		};
 
 		let v = Slicable::to_vec(&tx);
 		assert_eq!(UncheckedTransaction::from_slice(&mut &v[..]).unwrap(), tx);
 	}
+
+	#[test]
+	fn psbt_round_trips() {
+		let psbt = PartiallySignedTransaction::new(a_tx());
+
+		let v = Slicable::to_vec(&psbt);
+		assert_eq!(PartiallySignedTransaction::from_slice(&mut &v[..]).unwrap(), psbt);
+	}
+
+	#[test]
+	fn psbt_combine_and_finalize() {
+		let alice = [1; 32];
+		let bob = [2; 32];
+
+		let mut a = PartiallySignedTransaction::new(a_tx());
+		a.add_signature(alice, ::hash::H512([1; 64]));
+
+		let mut b = PartiallySignedTransaction::new(a_tx());
+		b.add_signature(bob, ::hash::H512([2; 64]));
+
+		a.combine(b).unwrap();
+
+		let finalized = a.finalize(&[alice, bob]).unwrap();
+		assert_eq!(finalized.signatures.len(), 2);
+	}
+
+	#[test]
+	fn psbt_combine_rejects_conflicting_signature() {
+		let alice = [1; 32];
+
+		let mut a = PartiallySignedTransaction::new(a_tx());
+		a.add_signature(alice, ::hash::H512([1; 64]));
+
+		let mut b = PartiallySignedTransaction::new(a_tx());
+		b.add_signature(alice, ::hash::H512([2; 64]));
+
+		assert_eq!(a.combine(b), Err(PsbtError::ConflictingSignature(alice)));
+	}
+
+	#[test]
+	fn psbt_finalize_rejects_missing_signature() {
+		let alice = [1; 32];
+		let bob = [2; 32];
+
+		let mut a = PartiallySignedTransaction::new(a_tx());
+		a.add_signature(alice, ::hash::H512([1; 64]));
+
+		assert_eq!(a.finalize(&[alice, bob]), Err(PsbtError::MissingSignature(bob)));
+	}
+
+	#[test]
+	fn psbt_decode_rejects_conflicting_duplicate_records() {
+		let alice = [1; 32];
+
+		let mut v = Slicable::to_vec(&a_tx());
+		for sig_byte in [1u8, 2u8].iter() {
+			v.push(32);
+			v.extend_from_slice(&alice);
+			v.push(64);
+			v.extend_from_slice(&[*sig_byte; 64]);
+		}
+		v.push(0);
+
+		assert_eq!(PartiallySignedTransaction::from_slice(&mut &v[..]), None);
+	}
+
+	#[test]
+	fn finalized_transaction_round_trips() {
+		let alice = [1; 32];
+		let bob = [2; 32];
+
+		let mut psbt = PartiallySignedTransaction::new(a_tx());
+		psbt.add_signature(alice, ::hash::H512([1; 64]));
+		psbt.add_signature(bob, ::hash::H512([2; 64]));
+		let finalized = psbt.finalize(&[alice, bob]).unwrap();
+
+		let v = Slicable::to_vec(&finalized);
+		assert_eq!(FinalizedTransaction::from_slice(&mut &v[..]).unwrap(), finalized);
+	}
 }